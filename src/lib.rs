@@ -38,20 +38,51 @@
    u32: magic header that must equal MANIFEST_OBJECT_MAGIC
    u32: object type, chosen from one of ManifestObjectType.
         if the type is EndOfList then the image stops here.
-   u64: size of object's contents in bytes.
+   u64: size of object's contents in bytes, as stored (see compression, below).
+   u32: CRC32 (IEEE) checksum of the object's contents, as stored. version 3 onward only.
+   u32: compression scheme used on the contents, chosen from CompressionScheme. version 4 onward only.
+   u64: size of object's contents once decompressed. version 4 onward only.
+   u32: target architecture of this object, chosen from Architecture. version 5 onward only.
    <NULL-terminated string of this object's unique name>
    <NULL-terminated string describing this object>
    <NULL padding to next 32-bit word>
    u32: number of properties (N) granted to this object
    N x <NULL-terminated property strings>
    <NULL padding to next 64-bit word>
-   <contents of the object as a byte stream>
+   <contents of the object as a byte stream, compressed if compression != None>
    <NULL padding to next 64-bit word>
 
     the object name could be a filename, or something other code can use to identify it.
     it should be unique among all the other objects in the dmfs image.
 
-    TODO: replace this with serial-deserialization, liek serdes? 
+    TODO: replace this with serial-deserialization, liek serdes?
+
+   version 6 onward, after the EndOfList bookend, the image ends with a name index:
+
+   then N x (one per object, sorted ascending by hash):
+   u64: FNV-1a 64-bit hash of the object's name
+   u64: absolute offset of the object's MANIFEST_OBJECT_MAGIC within the image
+
+   and finally a fixed-size footer so a reader can find the index from the end of the image:
+   u32: magic header that must equal MANIFEST_INDEX_MAGIC
+   u64: absolute offset where the name index begins
+   u32: number of entries (N) in the name index
+*/
+
+/* a manifest image can optionally be split into fixed-size physical chunks, for
+   boot media or transfer channels that cap individual blob sizes. each chunk
+   wraps a slice of the single logical image (as produced above) in its own
+   small header, so the chunks can be shipped and read back independently:
+
+   u32: magic header that must equal MANIFEST_PART_MAGIC
+   u32: this chunk's part index, counting from zero
+   u32: total number of parts the image was split into
+   u64: length in bytes of this chunk's slice of the logical image
+   <this chunk's slice of the logical image>
+
+   a reader gathers every chunk, checks they agree on the total part count and
+   between them cover every part index exactly once, then concatenates their
+   slices back into one logical image to iterate as normal. see ManifestParts.
 */
 
 #![no_std]
@@ -60,6 +91,12 @@
 extern crate alloc;
 extern crate byterider;
 
+#[cfg(feature = "zstd")]
+extern crate zstd_safe;
+
+#[cfg(feature = "elf")]
+extern crate goblin;
+
 use core::mem::size_of;
 use alloc::vec::Vec;
 use alloc::string::String;
@@ -69,20 +106,262 @@ use core::ops::Range;
 /* manifest image must start with the following */
 const MANIFEST_MAGIC: u32 = 0xD105C001;
 const MANIFEST_OBJECT_MAGIC: u32 = 0xD1015D4D;
-const MANIFEST_VERSION: u32 = 2; /* version supported */
+const MANIFEST_VERSION: u32 = 6; /* version supported */
 
 /* version history
    1 = 32-bit object content padding and 32-bit object sizes
    2 = 64-bit object content padding and 64-bit object sizes
+   3 = adds a CRC32 checksum of each object's contents, stored after the content size
+   4 = adds a per-object compression scheme and uncompressed content size
+   5 = adds a per-object target architecture tag
+   6 = adds a sorted name index and footer appended after the EndOfList bookend
 */
 
+/* marks the footer that points at the trailing name index, appended from version 6 onward */
+const MANIFEST_INDEX_MAGIC: u32 = 0xD105BEEF;
+
+/* size, in bytes, of the fixed-size footer: magic (u32) + index offset (u64) + entry count (u32) */
+const MANIFEST_INDEX_FOOTER_SIZE: usize = size_of::<u32>() + size_of::<u64>() + size_of::<u32>();
+
+/* size, in bytes, of the main image header: magic (u32) + version (u32) */
+const MANIFEST_HEADER_SIZE: usize = size_of::<u32>() + size_of::<u32>();
+
+/* marks the header of a single physical chunk of a split image */
+const MANIFEST_PART_MAGIC: u32 = 0xD105FA27;
+
 #[derive(Debug)]
 pub enum ManifestError
 {
     MalformedHeader, /* header is too small or malformed */
     BadMagic, /* unrecognized magic number in dmfs image header */
     VersionMismatch, /* dmfs image is of a later version than this code is aware of */
-    CantUseRegionHere /* trying to use a region of an image to generate an image */
+    CantUseRegionHere, /* trying to use a region of an image to generate an image */
+    ChecksumMismatch, /* an object's contents do not match its stored CRC32 checksum */
+    UnsupportedCompression, /* object uses a compression scheme not enabled via cargo features */
+    DecompressionFailed, /* the compression codec could not encode or decode the object's contents */
+    BufferTooSmall, /* caller-provided output buffer is smaller than the decompressed contents */
+    NotExecutable, /* tried to inspect an object that isn't a SystemService or GuestOS */
+    ElfParseError, /* the object's contents are not a well-formed ELF executable */
+    PartMismatch /* the supplied physical chunks don't form one complete, consistent image */
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionScheme
+{
+    None, /* contents are stored as-is */
+    Zstd /* contents are compressed with zstd */
+}
+
+impl CompressionScheme
+{
+    /* convert a compression scheme to an integer for the binary format */
+    pub fn to_integer(&self) -> u32
+    {
+        match self
+        {
+            CompressionScheme::None => 0,
+            CompressionScheme::Zstd => 1
+        }
+    }
+
+    pub fn from_integer(nr: u32) -> CompressionScheme
+    {
+        match nr
+        {
+            0 => CompressionScheme::None,
+            1 => CompressionScheme::Zstd,
+            _ => CompressionScheme::None
+        }
+    }
+}
+
+/* compress data using the given scheme. None always succeeds; Zstd
+   requires its cargo feature to be enabled.
+   note: LZMA was dropped from this enum. lzma_rs only exposes readers/writers
+   over std::io::{BufRead, Write}, which pulls in std and can't be reconciled
+   with this crate's #![no_std], so there's no way to wire it up as written */
+fn compress_bytes(data: &[u8], scheme: CompressionScheme) -> Result<Vec<u8>, ManifestError>
+{
+    match scheme
+    {
+        CompressionScheme::None => Ok(data.to_vec()),
+
+        #[cfg(feature = "zstd")]
+        CompressionScheme::Zstd =>
+        {
+            let mut out = alloc::vec![0u8; zstd_safe::compress_bound(data.len())];
+            let written = zstd_safe::compress(out.as_mut_slice(), data, 0).map_err(|_| ManifestError::DecompressionFailed)?;
+            out.truncate(written);
+            Ok(out)
+        },
+        #[cfg(not(feature = "zstd"))]
+        CompressionScheme::Zstd => Err(ManifestError::UnsupportedCompression)
+    }
+}
+
+/* decompress data using the given scheme, streaming the result into a caller-provided
+   buffer rather than returning a freshly allocated vector, to stay friendly to
+   no-heap-beyond-alloc targets. returns the number of bytes written into buf */
+fn decompress_bytes(data: &[u8], scheme: CompressionScheme, buf: &mut [u8]) -> Result<usize, ManifestError>
+{
+    match scheme
+    {
+        CompressionScheme::None =>
+        {
+            if buf.len() < data.len()
+            {
+                return Err(ManifestError::BufferTooSmall);
+            }
+            buf[..data.len()].copy_from_slice(data);
+            Ok(data.len())
+        },
+
+        /* zstd_safe streams the decompressed payload directly into buf, so no
+           intermediate heap allocation of the full decompressed size is needed */
+        #[cfg(feature = "zstd")]
+        CompressionScheme::Zstd => zstd_safe::decompress(buf, data).map_err(|_| ManifestError::DecompressionFailed),
+        #[cfg(not(feature = "zstd"))]
+        CompressionScheme::Zstd => Err(ManifestError::UnsupportedCompression)
+    }
+}
+
+/* compute the standard IEEE CRC32 (polynomial 0xEDB88320, reflected) of a byte slice */
+fn crc32_ieee(data: &[u8]) -> u32
+{
+    let mut crc: u32 = 0xFFFFFFFF;
+
+    for byte in data
+    {
+        crc ^= *byte as u32;
+        for _ in 0..8
+        {
+            crc = match crc & 1
+            {
+                1 => (crc >> 1) ^ 0xEDB88320,
+                _ => crc >> 1
+            };
+        }
+    }
+
+    crc ^ 0xFFFFFFFF
+}
+
+/* compute the 64-bit FNV-1a hash of a name, used to key entries in the name index */
+fn fnv1a_64(data: &[u8]) -> u64
+{
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in data
+    {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
+
+/* target CPU architecture an object was built for, modelled on the small
+   machine-type tags used by manifest/PE tooling */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Architecture
+{
+    I386,
+    X86_64,
+    Aarch64,
+    Riscv32,
+    Riscv64,
+    Unknown /* reserved for unrecognized or untagged objects */
+}
+
+impl Architecture
+{
+    /* convert an architecture to an integer for the binary format */
+    pub fn to_integer(&self) -> u32
+    {
+        match self
+        {
+            Architecture::I386    => 0,
+            Architecture::X86_64  => 1,
+            Architecture::Aarch64 => 2,
+            Architecture::Riscv32 => 3,
+            Architecture::Riscv64 => 4,
+            Architecture::Unknown => 5
+        }
+    }
+
+    pub fn from_integer(nr: u32) -> Architecture
+    {
+        match nr
+        {
+            0 => Architecture::I386,
+            1 => Architecture::X86_64,
+            2 => Architecture::Aarch64,
+            3 => Architecture::Riscv32,
+            4 => Architecture::Riscv64,
+            5 | _ => Architecture::Unknown
+        }
+    }
+
+    /* the architecture of the CPU this code is running on */
+    pub fn running() -> Architecture
+    {
+        #[cfg(target_arch = "x86")]
+        return Architecture::I386;
+
+        #[cfg(target_arch = "x86_64")]
+        return Architecture::X86_64;
+
+        #[cfg(target_arch = "aarch64")]
+        return Architecture::Aarch64;
+
+        #[cfg(target_arch = "riscv32")]
+        return Architecture::Riscv32;
+
+        #[cfg(target_arch = "riscv64")]
+        return Architecture::Riscv64;
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64",
+                       target_arch = "riscv32", target_arch = "riscv64")))]
+        return Architecture::Unknown;
+    }
+}
+
+/* one PT_LOAD program header of an ELF executable object */
+#[derive(Debug, Clone, Copy)]
+pub struct ElfSegment
+{
+    vaddr: u64, /* virtual address to map this segment at */
+    offset: u64, /* offset of this segment's bytes within the object's contents */
+    filesz: u64, /* number of bytes to copy from the object's contents */
+    memsz: u64, /* number of bytes to reserve in memory, zeroing any excess over filesz */
+    flags: u32 /* ELF segment flags, e.g. PF_R | PF_W | PF_X */
+}
+
+impl ElfSegment
+{
+    pub fn get_vaddr(&self) -> u64 { self.vaddr }
+    pub fn get_offset(&self) -> u64 { self.offset }
+    pub fn get_filesz(&self) -> u64 { self.filesz }
+    pub fn get_memsz(&self) -> u64 { self.memsz }
+    pub fn get_flags(&self) -> u32 { self.flags }
+}
+
+/* the parts of an ELF executable's header that a hypervisor needs to validate
+   and load a SystemService or GuestOS object */
+pub struct ElfInfo
+{
+    entry: u64, /* entry point address */
+    machine: u16, /* e_machine field, identifying the target architecture */
+    segments: Vec<ElfSegment> /* loadable (PT_LOAD) program headers, in file order */
+}
+
+impl ElfInfo
+{
+    pub fn get_entry_point(&self) -> u64 { self.entry }
+    pub fn get_machine(&self) -> u16 { self.machine }
+    pub fn get_loadable_segments(&self) -> &[ElfSegment] { &self.segments }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -151,7 +430,10 @@ pub struct ManifestObject
     name: String, /* unique identifier for this object */
     descr: String, /* user-friendly description of this object */
     properties: Vec<String>, /* list of properties granted to this object */
-    data: ManifestObjectData /* contents of the object */
+    data: ManifestObjectData, /* contents of the object, as stored (see compression) */
+    compression: CompressionScheme, /* compression scheme applied to the stored contents */
+    uncompressed_size: usize, /* size of the contents once decompressed */
+    arch: Architecture /* target CPU architecture this object was built for */
 }
 
 impl ManifestObject
@@ -164,12 +446,17 @@ impl ManifestObject
           props = array of properties assigned to the object, or None */
     pub fn new(objtype: ManifestObjectType, name: String, descr: String, data: ManifestObjectData, properties: Option<Vec<String>>) -> ManifestObject
     {
+        let uncompressed_size = data.len();
+
         ManifestObject
         {
             objtype,
             name,
             descr,
             data,
+            uncompressed_size,
+            compression: CompressionScheme::None,
+            arch: Architecture::running(),
             properties: match properties
             {
                 Some(p) => p,
@@ -178,12 +465,33 @@ impl ManifestObject
         }
     }
 
+    /* mark this object to be stored compressed with the given scheme when the
+       manifest is turned into an image. on an object read from an image, this does not
+       change the stored contents, but it does change the scheme that get_compression()
+       reports and that ManifestImageIter::decompress() uses to decode them */
+    pub fn with_compression(mut self, scheme: CompressionScheme) -> ManifestObject
+    {
+        self.compression = scheme;
+        self
+    }
+
+    /* tag this object as built for the given target architecture, rather than
+       the architecture of the host building the manifest */
+    pub fn with_arch(mut self, arch: Architecture) -> ManifestObject
+    {
+        self.arch = arch;
+        self
+    }
+
     pub fn get_type(&self) -> ManifestObjectType { self.objtype }
     pub fn get_name(&self) -> String { self.name.clone() }
     pub fn get_description(&self) -> String { self.descr.clone() }
     pub fn get_properties(&self) -> Vec<String> { self.properties.clone() }
     pub fn get_contents(&self) -> &ManifestObjectData { &self.data }
     pub fn get_contents_size(&self) -> usize { self.data.len() }
+    pub fn get_compression(&self) -> CompressionScheme { self.compression }
+    pub fn get_arch(&self) -> Architecture { self.arch }
+    pub fn get_uncompressed_size(&self) -> usize { self.uncompressed_size }
 }
 
 /* high-level definition of a system manifest */
@@ -217,14 +525,33 @@ impl Manifest
         b.add_u32(MANIFEST_MAGIC);
         b.add_u32(MANIFEST_VERSION);
 
+        /* (name hash, absolute offset of MANIFEST_OBJECT_MAGIC) for the trailing name index */
+        let mut index_entries: Vec<(u64, u64)> = Vec::with_capacity(self.objects.len());
+
         for object in &self.objects
         {
+            index_entries.push((fnv1a_64(object.get_name().as_bytes()), b.len() as u64));
+
             /* include magic for integrity check reasons */
             b.add_u32(MANIFEST_OBJECT_MAGIC);
 
+            /* compress the contents now (if requested) so the header can describe
+               the stored size, its checksum, and the original uncompressed size */
+            let original_bytes = match object.get_contents()
+            {
+                ManifestObjectData::Bytes(bytes) => bytes,
+                ManifestObjectData::Region(_) => return Err(ManifestError::CantUseRegionHere)
+            };
+            let stored_bytes = compress_bytes(original_bytes, object.get_compression())?;
+
             /* stream out the object data */
             b.add_u32(object.get_type().to_integer());
-            b.add_u64(object.get_contents_size() as u64);
+            b.add_u64(stored_bytes.len() as u64);
+            b.add_u32(crc32_ieee(&stored_bytes));
+            b.add_u32(object.get_compression().to_integer());
+            b.add_u64(original_bytes.len() as u64);
+            b.add_u32(object.get_arch().to_integer());
+
             b.add_null_term_string(object.get_name().as_str());
             b.add_null_term_string(object.get_description().as_str());
             b.pad_to_u32();
@@ -234,52 +561,126 @@ impl Manifest
             b.add_u32(object.properties.len() as u32);
             for property in &object.properties
             {
-                b.add_null_term_string(property.as_str());   
+                b.add_null_term_string(property.as_str());
             }
             b.pad_to_u64();
 
-            /* copy object bytes into the image */
-            match object.get_contents()
+            /* copy the (possibly compressed) object bytes into the image */
+            for byte in &stored_bytes
             {
-                ManifestObjectData::Bytes(bytes) =>
-                {
-                    for byte in bytes
-                    {
-                        b.add_u8(*byte);
-                    }
-                    b.pad_to_u64();
-                },
-
-                _ => return Err(ManifestError::CantUseRegionHere)
+                b.add_u8(*byte);
             }
+            b.pad_to_u64();
         }
 
         /* add the bookend type */
         b.add_u32(ManifestObjectType::EndOfList.to_integer());
 
+        /* append the sorted name index and its footer, so ManifestImageIter::find()
+           can binary-search straight to a named object instead of scanning the image */
+        index_entries.sort_by_key(|(hash, _)| *hash);
+        let index_offset = b.len() as u64;
+
+        for (hash, offset) in &index_entries
+        {
+            b.add_u64(*hash);
+            b.add_u64(*offset);
+        }
+
+        b.add_u32(MANIFEST_INDEX_MAGIC);
+        b.add_u64(index_offset);
+        b.add_u32(index_entries.len() as u32);
+
         Ok(b)
     }
+
+    /* build the image as a sequence of physical chunks, none of whose payload
+       exceeds max_payload_size bytes, for boot media or transfer channels that
+       cap individual blob sizes. chunks are emitted in order, part 0 first */
+    pub fn to_image_parts(&self, max_payload_size: usize) -> Result<Vec<Bytes>, ManifestError>
+    {
+        if max_payload_size == 0
+        {
+            return Err(ManifestError::MalformedHeader);
+        }
+
+        let image = self.to_image()?;
+        let image_bytes = image.as_slice();
+
+        let whole_chunks = image_bytes.chunks(max_payload_size);
+        let total_parts = whole_chunks.len().max(1) as u32;
+        let mut parts = Vec::with_capacity(total_parts as usize);
+
+        for (part_index, payload) in whole_chunks.enumerate()
+        {
+            let mut part = Bytes::new();
+            part.add_u32(MANIFEST_PART_MAGIC);
+            part.add_u32(part_index as u32);
+            part.add_u32(total_parts);
+            part.add_u64(payload.len() as u64);
+            for byte in payload
+            {
+                part.add_u8(*byte);
+            }
+            parts.push(part);
+        }
+
+        Ok(parts)
+    }
 }
 
-/* define an iterator over a manifest image in memory */
-pub struct ManifestImageIter
+/* read a little-endian u32 out of a slice at the given offset, or None if it doesn't fit */
+fn slice_read_u32(data: &[u8], offset: usize) -> Option<u32>
+{
+    let end = offset.checked_add(size_of::<u32>())?;
+    let bytes: [u8; size_of::<u32>()] = data.get(offset..end)?.try_into().ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+/* read a little-endian u64 out of a slice at the given offset, or None if it doesn't fit */
+fn slice_read_u64(data: &[u8], offset: usize) -> Option<u64>
+{
+    let end = offset.checked_add(size_of::<u64>())?;
+    let bytes: [u8; size_of::<u64>()] = data.get(offset..end)?.try_into().ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
+
+/* read a NULL-terminated UTF-8 string out of a slice starting at offset.
+   returns None if no NULL byte is found before the end of the slice */
+fn slice_read_null_term_string(data: &[u8], offset: usize) -> Option<String>
+{
+    let slice = data.get(offset..)?;
+    let nul_pos = slice.iter().position(|b| *b == 0)?;
+    String::from_utf8(slice[..nul_pos].to_vec()).ok()
+}
+
+/* round an offset up to the next 32-bit boundary */
+fn slice_align_to_next_u32(offset: usize) -> usize { (offset + 3) & !3 }
+
+/* round an offset up to the next 64-bit boundary */
+fn slice_align_to_next_u64(offset: usize) -> usize { (offset + 7) & !7 }
+
+/* define an iterator over a manifest image in memory, borrowing its bytes
+   rather than copying them, so gigabyte-scale images can be walked without
+   cloning them onto the heap first */
+pub struct ManifestImageIter<'a>
 {
     offset: usize,
-    bytes: Bytes,
+    version: u32,
+    verify_checksums: bool,
+    bytes: &'a [u8],
 }
 
-impl ManifestImageIter
+impl<'a> ManifestImageIter<'a>
 {
-    /* create manifest image in memory from byte slice */
-    pub fn from_slice(blob: &[u8]) -> Result<ManifestImageIter, ManifestError>
+    /* create manifest image iterator directly over a byte slice, without copying it */
+    pub fn from_slice(blob: &'a [u8]) -> Result<ManifestImageIter<'a>, ManifestError>
     {
-        /* this is horrendously expensive. FIXME: can we do this without copying MBs of data? */
-        let bytes = Bytes::from_slice(blob);
         let mut offset = 0;
         let width = size_of::<u32>();
 
         /* compliance checks */
-        match bytes.read_u32(offset)
+        match slice_read_u32(blob, offset)
         {
             Some(magic) => if magic != MANIFEST_MAGIC
             {
@@ -292,7 +693,7 @@ impl ManifestImageIter
             None => return Err(ManifestError::MalformedHeader)
         };
 
-        match bytes.read_u32(offset)
+        let version = match slice_read_u32(blob, offset)
         {
             Some(version) => if version > MANIFEST_VERSION
             {
@@ -301,32 +702,118 @@ impl ManifestImageIter
             else
             {
                 offset = offset + width;
+                version
             },
             None => return Err(ManifestError::MalformedHeader)
         };
 
         Ok(ManifestImageIter
         {
-            bytes,
+            bytes: blob,
+            version,
+            verify_checksums: true,
             offset /* skip header */
         })
     }
+
+    /* disable per-object CRC32 verification, e.g. when walking a trusted image
+       at speed matters more than catching storage corruption */
+    pub fn without_checksum_verification(mut self) -> ManifestImageIter<'a>
+    {
+        self.verify_checksums = false;
+        self
+    }
+
+    /* filter this iterator down to objects tagged for the given architecture, so a
+       multi-arch image can be walked for just the objects the running CPU can use.
+       errors are passed through rather than silently dropped.
+       lives here on ManifestImageIter rather than as Manifest::for_arch, because
+       filtering is a read-side concern: Manifest only ever describes an image to
+       be written, and has no notion of "the objects in this image" to filter over */
+    pub fn for_arch(self, arch: Architecture) -> impl Iterator<Item = Result<ManifestObject, ManifestError>> + 'a
+    {
+        self.filter(move |result| match result
+        {
+            Ok(object) => object.get_arch() == arch,
+            Err(_) => true
+        })
+    }
+
+    /* fetch the raw contents of an object as a borrowed sub-slice of the image,
+       without copying. returns None if the object's region doesn't fit in the image,
+       or if the object's contents are a standalone Bytes vector rather than a region */
+    pub fn get_contents(&self, object: &ManifestObject) -> Option<&'a [u8]>
+    {
+        match object.get_contents()
+        {
+            ManifestObjectData::Region(region) => self.bytes.get(region.clone()),
+            ManifestObjectData::Bytes(_) => None
+        }
+    }
+
+    /* decompress an object's contents into a caller-provided buffer, which must be
+       at least object.get_uncompressed_size() bytes long. returns the number of
+       bytes written. objects with CompressionScheme::None are simply copied */
+    pub fn decompress(&self, object: &ManifestObject, buf: &mut [u8]) -> Result<usize, ManifestError>
+    {
+        let stored = self.get_contents(object).ok_or(ManifestError::CantUseRegionHere)?;
+        decompress_bytes(stored, object.get_compression(), buf)
+    }
+
+    /* parse a SystemService or GuestOS object's contents as an ELF executable, returning
+       its entry point, target machine, and loadable program headers. this lets a caller
+       validate a guest's word width and map its segments without a second parsing pass.
+       lives here on ManifestImageIter, not ManifestObject, because a Region object holds
+       no bytes of its own: only the iterator that produced it can resolve the region
+       back into a borrowed slice to parse */
+    #[cfg(feature = "elf")]
+    pub fn elf_info(&self, object: &ManifestObject) -> Result<ElfInfo, ManifestError>
+    {
+        match object.get_type()
+        {
+            ManifestObjectType::SystemService | ManifestObjectType::GuestOS => (),
+            _ => return Err(ManifestError::NotExecutable)
+        };
+
+        let contents = self.get_contents(object).ok_or(ManifestError::CantUseRegionHere)?;
+        let elf = goblin::elf::Elf::parse(contents).map_err(|_| ManifestError::ElfParseError)?;
+
+        let segments = elf.program_headers.iter()
+            .filter(|ph| ph.p_type == goblin::elf::program_header::PT_LOAD)
+            .map(|ph| ElfSegment
+            {
+                vaddr: ph.p_vaddr,
+                offset: ph.p_offset,
+                filesz: ph.p_filesz,
+                memsz: ph.p_memsz,
+                flags: ph.p_flags
+            })
+            .collect();
+
+        Ok(ElfInfo
+        {
+            entry: elf.entry,
+            machine: elf.header.e_machine,
+            segments
+        })
+    }
 }
 
-/* spin through all the objects in the manifest */
-impl Iterator for ManifestImageIter
+impl<'a> ManifestImageIter<'a>
 {
-    type Item = ManifestObject;
-
-    fn next(&mut self) -> Option<ManifestObject>
+    /* decode the object starting at the given absolute offset, which must point at its
+       MANIFEST_OBJECT_MAGIC. returns the decoded object (or a checksum error) along with
+       the offset of the next object, or None if there's no valid object at offset
+       (e.g. it's the EndOfList bookend, or off the end of the image). shared by next()
+       and find(), so a caller can seek straight to an object via the name index */
+    fn decode_at(&self, offset: usize) -> Option<(Result<ManifestObject, ManifestError>, usize)>
     {
-        /* pick up from where we were last at */
-        let mut offset = self.offset;
+        let mut offset = offset;
         let width = size_of::<u32>();
         let double_width = size_of::<u64>();
 
         /* make sure the magic matches for this object, or bail */
-        if self.bytes.read_u32(offset)? != MANIFEST_OBJECT_MAGIC
+        if slice_read_u32(self.bytes, offset)? != MANIFEST_OBJECT_MAGIC
         {
             return None;
         }
@@ -334,58 +821,467 @@ impl Iterator for ManifestImageIter
 
         /* extract the object's meta data.
         end the iteration if we reach an EndOfList placeholder object */
-        let obj_type = match ManifestObjectType::from_integer(self.bytes.read_u32(offset)?)
+        let obj_type = match ManifestObjectType::from_integer(slice_read_u32(self.bytes, offset)?)
         {
             ManifestObjectType::EndOfList => return None,
             t => t
         };
         offset = offset + width;
 
-        let obj_size = self.bytes.read_u64(offset)?;
+        let obj_size = slice_read_u64(self.bytes, offset)?;
         offset = offset + double_width;
 
-        let obj_name = self.bytes.read_null_term_string(offset)?;
+        /* version 3 onward stores a CRC32 of the contents right after the size */
+        let obj_crc = if self.version >= 3
+        {
+            let crc = slice_read_u32(self.bytes, offset)?;
+            offset = offset + width;
+            Some(crc)
+        }
+        else
+        {
+            None
+        };
+
+        /* version 4 onward stores the compression scheme and uncompressed size */
+        let (obj_compression, obj_uncompressed_size) = if self.version >= 4
+        {
+            let compression = CompressionScheme::from_integer(slice_read_u32(self.bytes, offset)?);
+            offset = offset + width;
+            let uncompressed_size = slice_read_u64(self.bytes, offset)?;
+            offset = offset + double_width;
+            (compression, uncompressed_size as usize)
+        }
+        else
+        {
+            (CompressionScheme::None, 0)
+        };
+
+        /* version 5 onward tags each object with its target architecture */
+        let obj_arch = if self.version >= 5
+        {
+            let arch = Architecture::from_integer(slice_read_u32(self.bytes, offset)?);
+            offset = offset + width;
+            arch
+        }
+        else
+        {
+            Architecture::Unknown
+        };
+
+        let obj_name = slice_read_null_term_string(self.bytes, offset)?;
         offset = offset + obj_name.len() + 1; // don't forget the null byte
 
-        let obj_desc = self.bytes.read_null_term_string(offset)?;
+        let obj_desc = slice_read_null_term_string(self.bytes, offset)?;
         offset = offset + obj_desc.len() + 1; // don't forget the null byte
-        offset = Bytes::align_to_next_u32(offset);
+        offset = slice_align_to_next_u32(offset);
 
-        let obj_property_count = self.bytes.read_u32(offset)?;
+        let obj_property_count = slice_read_u32(self.bytes, offset)?;
         offset = offset + width;
         let mut obj_props = Vec::new();
 
         for _ in 0..obj_property_count
         {
-            let prop_string = self.bytes.read_null_term_string(offset)?;
+            let prop_string = slice_read_null_term_string(self.bytes, offset)?;
             offset = offset + prop_string.len() + 1; // don't forget the null byte
             obj_props.push(prop_string);
         }
-        offset = Bytes::align_to_next_u64(offset);
+        offset = slice_align_to_next_u64(offset);
 
         /* define the region of the image that contains the object's contents */
         let region = Range { start: offset, end: offset + obj_size as usize };
+        if region.end > self.bytes.len()
+        {
+            return None;
+        }
+
+        /* this is where the next object (or bookend) starts */
+        let next_offset = slice_align_to_next_u64(offset + obj_size as usize);
+
+        if self.verify_checksums
+        {
+            if let Some(expected_crc) = obj_crc
+            {
+                if crc32_ieee(&self.bytes[region.clone()]) != expected_crc
+                {
+                    return Some((Err(ManifestError::ChecksumMismatch), next_offset));
+                }
+            }
+        }
 
-        /* save the offset for the next time round */
-        self.offset = Bytes::align_to_next_u64(offset + obj_size as usize);
+        /* pre-version-4 images carry no compression, so the uncompressed size is just
+           the size of the (uncompressed) stored region */
+        let obj_uncompressed_size = match self.version >= 4
+        {
+            true => obj_uncompressed_size,
+            false => region.end - region.start
+        };
 
-        Some(ManifestObject
+        Some((Ok(ManifestObject
         {
             objtype: obj_type,
             name: obj_name,
             descr: obj_desc,
             properties: obj_props,
-            data: ManifestObjectData::Region(region)
-        })
+            data: ManifestObjectData::Region(region),
+            compression: obj_compression,
+            uncompressed_size: obj_uncompressed_size,
+            arch: obj_arch
+        }), next_offset))
+    }
+
+    /* look up a single object by name, using the trailing name index for an O(log n)
+       binary search when the image carries one (version 6 onward), and falling back
+       to a linear scan from the start of the image otherwise */
+    pub fn find(&self, name: &str) -> Option<Result<ManifestObject, ManifestError>>
+    {
+        match self.index_entries()
+        {
+            Some(entries) =>
+            {
+                let target = fnv1a_64(name.as_bytes());
+                let pos = entries.binary_search_by_key(&target, |(hash, _)| *hash).ok()?;
+                let (_, offset) = entries[pos];
+                let (result, _) = self.decode_at(offset as usize)?;
+
+                /* the index is keyed on name hash, not name, so confirm the decoded
+                   object really is the one we're after before handing it back:
+                   otherwise a hash collision could return the wrong object, or a
+                   miss whose hash collides with a present object could return Some
+                   instead of None */
+                match &result
+                {
+                    Ok(object) if object.get_name() == name => Some(result),
+                    _ => None
+                }
+            },
+            None => self.find_linear(name)
+        }
+    }
+
+    /* scan every object in the image from the start, looking for one by name */
+    fn find_linear(&self, name: &str) -> Option<Result<ManifestObject, ManifestError>>
+    {
+        let mut offset = MANIFEST_HEADER_SIZE;
+
+        loop
+        {
+            let (result, next_offset) = self.decode_at(offset)?;
+            if let Ok(object) = &result
+            {
+                if object.get_name() == name
+                {
+                    return Some(result);
+                }
+            }
+            offset = next_offset;
+        }
+    }
+
+    /* parse the trailing name index, if the image carries one. entries are
+       returned sorted ascending by name hash, ready for a binary search */
+    fn index_entries(&self) -> Option<Vec<(u64, u64)>>
+    {
+        if self.bytes.len() < MANIFEST_INDEX_FOOTER_SIZE
+        {
+            return None;
+        }
+
+        let footer_offset = self.bytes.len() - MANIFEST_INDEX_FOOTER_SIZE;
+        if slice_read_u32(self.bytes, footer_offset)? != MANIFEST_INDEX_MAGIC
+        {
+            return None;
+        }
+
+        let index_offset = slice_read_u64(self.bytes, footer_offset + size_of::<u32>())? as usize;
+        let count = slice_read_u32(self.bytes, footer_offset + size_of::<u32>() + size_of::<u64>())? as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        let mut offset = index_offset;
+
+        for _ in 0..count
+        {
+            let hash = slice_read_u64(self.bytes, offset)?;
+            offset = offset + size_of::<u64>();
+            let obj_offset = slice_read_u64(self.bytes, offset)?;
+            offset = offset + size_of::<u64>();
+            entries.push((hash, obj_offset));
+        }
+
+        Some(entries)
+    }
+}
+
+/* spin through all the objects in the manifest, verifying each object's
+   CRC32 checksum as it goes unless verification has been disabled */
+impl<'a> Iterator for ManifestImageIter<'a>
+{
+    type Item = Result<ManifestObject, ManifestError>;
+
+    fn next(&mut self) -> Option<Result<ManifestObject, ManifestError>>
+    {
+        let (result, next_offset) = self.decode_at(self.offset)?;
+        self.offset = next_offset;
+        Some(result)
+    }
+}
+
+/* gathers the physical chunks of a split image (see Manifest::to_image_parts) back
+   into one logical image, so it can be iterated exactly as if it had never been
+   split. the chunks may be handed over in any order, as they might arrive off
+   storage or a transfer channel out of sequence */
+pub struct ManifestParts
+{
+    image: Vec<u8>
+}
+
+impl ManifestParts
+{
+    /* reassemble a complete set of physical chunks into one logical image.
+       fails if the chunks don't agree on the total part count, or don't
+       between them cover every part index exactly once */
+    pub fn from_slices(parts: &[&[u8]]) -> Result<ManifestParts, ManifestError>
+    {
+        if parts.is_empty()
+        {
+            return Err(ManifestError::PartMismatch);
+        }
+
+        let width = size_of::<u32>();
+        let double_width = size_of::<u64>();
+
+        /* (part index, total parts claimed, this chunk's payload slice) */
+        let mut parsed: Vec<(u32, u32, &[u8])> = Vec::with_capacity(parts.len());
+
+        for part in parts
+        {
+            let mut offset = 0;
+
+            let magic = slice_read_u32(part, offset).ok_or(ManifestError::MalformedHeader)?;
+            if magic != MANIFEST_PART_MAGIC
+            {
+                return Err(ManifestError::BadMagic);
+            }
+            offset = offset + width;
+
+            let index = slice_read_u32(part, offset).ok_or(ManifestError::MalformedHeader)?;
+            offset = offset + width;
+
+            let total = slice_read_u32(part, offset).ok_or(ManifestError::MalformedHeader)?;
+            offset = offset + width;
+
+            let payload_len = slice_read_u64(part, offset).ok_or(ManifestError::MalformedHeader)? as usize;
+            offset = offset + double_width;
+
+            let payload = part.get(offset..offset + payload_len).ok_or(ManifestError::MalformedHeader)?;
+
+            parsed.push((index, total, payload));
+        }
+
+        let total_parts = parsed[0].1;
+        if parsed.len() != total_parts as usize || parsed.iter().any(|(_, total, _)| *total != total_parts)
+        {
+            return Err(ManifestError::PartMismatch);
+        }
+
+        parsed.sort_by_key(|(index, _, _)| *index);
+        for (expected_index, (index, _, _)) in parsed.iter().enumerate()
+        {
+            if *index != expected_index as u32
+            {
+                return Err(ManifestError::PartMismatch);
+            }
+        }
+
+        let mut image = Vec::new();
+        for (_, _, payload) in &parsed
+        {
+            image.extend_from_slice(payload);
+        }
+
+        Ok(ManifestParts { image })
+    }
+
+    /* iterate the reassembled image exactly as if it had arrived as a single blob */
+    pub fn iter(&self) -> Result<ManifestImageIter<'_>, ManifestError>
+    {
+        ManifestImageIter::from_slice(&self.image)
     }
 }
 
 #[cfg(test)]
 mod tests
 {
+    use super::*;
+
     #[test]
     fn it_works()
     {
         assert_eq!(2 + 2, 4);
     }
+
+    /* the zero-copy reader in slice_read_u32/slice_read_u64 assumes byterider's
+       add_u32/add_u64 encode little-endian. check the raw bytes it emits, not just
+       that slice_read agrees with itself, so a big-endian byterider would be caught */
+    #[test]
+    fn byterider_is_little_endian()
+    {
+        let mut b = Bytes::new();
+        b.add_u32(0x01020304);
+        b.add_u64(0x0102030405060708);
+        let bytes = b.as_slice();
+
+        assert_eq!(bytes[0], 0x04);
+        assert_eq!(bytes[1], 0x03);
+        assert_eq!(bytes[2], 0x02);
+        assert_eq!(bytes[3], 0x01);
+        assert_eq!(slice_read_u32(bytes, 0), Some(0x01020304));
+
+        assert_eq!(bytes[4], 0x08);
+        assert_eq!(bytes[11], 0x01);
+        assert_eq!(slice_read_u64(bytes, 4), Some(0x0102030405060708));
+    }
+
+    /* flipping a bit in an object's stored contents should be caught by its CRC32,
+       and that check should be skippable via without_checksum_verification() */
+    #[test]
+    fn crc_detects_corruption()
+    {
+        let mut manifest = Manifest::new();
+        manifest.add(ManifestObject::new(
+            ManifestObjectType::SystemService,
+            String::from("svc"),
+            String::from("a test service"),
+            ManifestObjectData::Bytes(alloc::vec![0xAAu8; 16]),
+            None));
+
+        let image = manifest.to_image().unwrap();
+        let bytes = image.as_slice();
+
+        /* sanity check: the untouched image verifies cleanly */
+        let object = ManifestImageIter::from_slice(bytes).unwrap().next().unwrap().unwrap();
+        assert_eq!(object.get_name(), "svc");
+
+        /* flip a bit inside the object's stored content (the run of 0xAA bytes) */
+        let mut corrupted = bytes.to_vec();
+        let content_offset = corrupted.iter().position(|b| *b == 0xAA).unwrap();
+        corrupted[content_offset] ^= 0x01;
+
+        let mut iter = ManifestImageIter::from_slice(&corrupted).unwrap();
+        assert!(matches!(iter.next(), Some(Err(ManifestError::ChecksumMismatch))));
+
+        /* disabling verification should let the (now corrupt) contents through */
+        let mut iter = ManifestImageIter::from_slice(&corrupted).unwrap().without_checksum_verification();
+        assert!(iter.next().unwrap().is_ok());
+    }
+
+    /* a Zstd-compressed object should decompress back to its original bytes, and
+       its stored (compressed) contents should actually be smaller than the original
+       for compressible input, otherwise compression isn't doing anything */
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trips()
+    {
+        let original = alloc::vec![0x42u8; 4096];
+
+        let mut manifest = Manifest::new();
+        manifest.add(ManifestObject::new(
+            ManifestObjectType::GuestOS,
+            String::from("guest"),
+            String::from("a test guest"),
+            ManifestObjectData::Bytes(original.clone()),
+            None).with_compression(CompressionScheme::Zstd));
+
+        let image = manifest.to_image().unwrap();
+        let mut iter = ManifestImageIter::from_slice(image.as_slice()).unwrap();
+        let object = iter.next().unwrap().unwrap();
+
+        assert_eq!(object.get_compression(), CompressionScheme::Zstd);
+        assert_eq!(object.get_uncompressed_size(), original.len());
+        assert!(object.get_contents_size() < original.len());
+
+        let mut decompressed = alloc::vec![0u8; object.get_uncompressed_size()];
+        let written = iter.decompress(&object, &mut decompressed).unwrap();
+        assert_eq!(written, original.len());
+        assert_eq!(decompressed, original);
+    }
+
+    /* find() should agree with a manual linear scan for every present name, and
+       return None for an absent one, whether or not it takes the indexed path */
+    #[test]
+    fn find_matches_linear_scan()
+    {
+        let mut manifest = Manifest::new();
+        for name in ["alpha", "beta", "gamma"]
+        {
+            manifest.add(ManifestObject::new(
+                ManifestObjectType::SystemService,
+                String::from(name),
+                String::from("a test service"),
+                ManifestObjectData::Bytes(alloc::vec![name.len() as u8; 8]),
+                None));
+        }
+
+        let image = manifest.to_image().unwrap();
+        let iter = ManifestImageIter::from_slice(image.as_slice()).unwrap();
+
+        for name in ["alpha", "beta", "gamma"]
+        {
+            let found = iter.find(name).unwrap().unwrap();
+            let scanned = iter.find_linear(name).unwrap().unwrap();
+            assert_eq!(found.get_name(), name);
+            assert_eq!(found.get_name(), scanned.get_name());
+        }
+
+        assert!(iter.find("does-not-exist").is_none());
+        assert!(iter.find_linear("does-not-exist").is_none());
+    }
+
+    /* splitting an image into small physical chunks and reassembling them should
+       present exactly the same objects as iterating the unsplit image */
+    #[test]
+    fn split_image_reassembles()
+    {
+        let mut manifest = Manifest::new();
+        manifest.add(ManifestObject::new(
+            ManifestObjectType::BootMsg,
+            String::from("welcome"),
+            String::from("a boot banner"),
+            ManifestObjectData::Bytes(alloc::vec![0x11u8; 256]),
+            None));
+        manifest.add(ManifestObject::new(
+            ManifestObjectType::GuestOS,
+            String::from("guest"),
+            String::from("a test guest"),
+            ManifestObjectData::Bytes(alloc::vec![0x22u8; 256]),
+            None));
+
+        let whole_image = manifest.to_image().unwrap();
+        let expected: Vec<String> = ManifestImageIter::from_slice(whole_image.as_slice())
+            .unwrap()
+            .map(|result| result.unwrap().get_name())
+            .collect();
+
+        /* chop it up small enough that an object's contents straddle a chunk boundary */
+        let parts = manifest.to_image_parts(64).unwrap();
+        assert!(parts.len() > 1);
+
+        let part_slices: Vec<&[u8]> = parts.iter().map(|p| p.as_slice()).collect();
+        let reassembled = ManifestParts::from_slices(&part_slices).unwrap();
+
+        let actual: Vec<String> = reassembled.iter().unwrap()
+            .map(|result| result.unwrap().get_name())
+            .collect();
+
+        assert_eq!(actual, expected);
+
+        /* the parts needn't arrive in order: reassembly should still succeed */
+        let mut shuffled = part_slices.clone();
+        shuffled.reverse();
+        let reassembled_shuffled = ManifestParts::from_slices(&shuffled).unwrap();
+        let actual_shuffled: Vec<String> = reassembled_shuffled.iter().unwrap()
+            .map(|result| result.unwrap().get_name())
+            .collect();
+        assert_eq!(actual_shuffled, expected);
+    }
 }